@@ -1,6 +1,48 @@
+use instant::Instant;
 use winit::event::*;
 
-pub struct Camera {
+// Lets `CameraController` drive different camera styles (FPS-style,
+// orbit, ...) through the same input handling code.
+pub trait Camera {
+    fn get_view_projection_matrix(&self) -> glm::Mat4;
+    fn set_aspect(&mut self, aspect: f32);
+    fn get_front(&self) -> glm::Vec3;
+    fn get_world_up(&self) -> glm::Vec3;
+    fn translate(&mut self, offset: glm::Vec3);
+    fn rotate(&mut self, dyaw: f32, dpitch: f32);
+    fn zoom(&mut self, delta: f32);
+
+    fn resize(&mut self, width: u32, height: u32) {
+        if height == 0 {
+            return;
+        }
+        self.set_aspect(width as f32 / height as f32);
+    }
+}
+
+// Shared by `FpsCamera`/`OrbitCamera`'s `rotate` impls: advances yaw/pitch
+// and keeps them in valid ranges regardless of which fields drive the view.
+fn rotate_yaw_pitch(yaw: &mut f32, pitch: &mut f32, dyaw: f32, dpitch: f32) {
+    *yaw += dyaw;
+    *yaw %= 2.0 * std::f32::consts::PI;
+    *pitch += dpitch;
+    *pitch = pitch.clamp(-89.0_f32.to_radians(), 89.0_f32.to_radians());
+}
+
+fn opengl_to_wgpu_matrix() -> glm::Mat4 {
+    use lazy_static::lazy_static;
+    lazy_static! {
+        pub static ref OPENGL_TO_WGPU_MATRIX: glm::Mat4 = glm::mat4(
+            1.0, 0.0, 0.0, 0.0,
+            0.0, 1.0, 0.0, 0.0,
+            0.0, 0.0, 0.5, 0.0,
+            0.0, 0.0, 0.5, 1.0,
+        );
+    }
+    *OPENGL_TO_WGPU_MATRIX
+}
+
+pub struct FpsCamera {
     pub position: glm::Vec3,
     pub world_up: glm::Vec3,
     pub yaw: f32,
@@ -11,7 +53,7 @@ pub struct Camera {
     pub zfar: f32,
 }
 
-impl Camera {
+impl FpsCamera {
     pub fn new(
         position: glm::Vec3,
         world_up: glm::Vec3,
@@ -21,7 +63,7 @@ impl Camera {
         znear: f32,
         zfar: f32,
     ) -> Self {
-        let mut res = Camera {
+        let mut res = FpsCamera {
             position,
             world_up,
             yaw: 0.0,
@@ -40,8 +82,29 @@ impl Camera {
         self.yaw = dir.z.atan2(dir.x);
         self.pitch = dir.y.asin();
     }
+}
 
-    pub fn get_front(&self) -> glm::Vec3 {
+impl Camera for FpsCamera {
+    fn get_view_projection_matrix(&self) -> glm::Mat4 {
+        let view = glm::look_at(
+            &self.position,
+            &(self.position + self.get_front()),
+            &self.world_up
+        );
+        let projection = glm::perspective(
+            self.aspect,
+            self.fovy,
+            self.znear,
+            self.zfar
+        );
+        opengl_to_wgpu_matrix() * projection * view
+    }
+
+    fn set_aspect(&mut self, aspect: f32) {
+        self.aspect = aspect;
+    }
+
+    fn get_front(&self) -> glm::Vec3 {
         glm::vec3(
             self.yaw.cos() * self.pitch.cos(),
             self.pitch.sin(),
@@ -49,28 +112,119 @@ impl Camera {
         )
     }
 
-    pub fn get_view_projection_matrix(&self) -> glm::Mat4 {
-        use lazy_static::lazy_static;
-        lazy_static! {
-            pub static ref OPENGL_TO_WGPU_MATRIX: glm::Mat4 = glm::mat4(
-                1.0, 0.0, 0.0, 0.0,
-                0.0, 1.0, 0.0, 0.0,
-                0.0, 0.0, 0.5, 0.0,
-                0.0, 0.0, 0.5, 1.0,
-            );
+    fn get_world_up(&self) -> glm::Vec3 {
+        self.world_up
+    }
+
+    fn translate(&mut self, offset: glm::Vec3) {
+        self.position += offset;
+    }
+
+    fn rotate(&mut self, dyaw: f32, dpitch: f32) {
+        rotate_yaw_pitch(&mut self.yaw, &mut self.pitch, dyaw, dpitch);
+    }
+
+    // Positive `delta` zooms in (narrower fovy), matching `OrbitCamera`.
+    fn zoom(&mut self, delta: f32) {
+        self.fovy -= delta;
+        self.fovy = self.fovy.clamp(1.0_f32.to_radians(), 120.0_f32.to_radians());
+    }
+}
+
+// An arcball-style camera that orbits around a `target` point at a given
+// `distance`, rather than flying freely through the scene; translating it
+// pans the `target`.
+pub struct OrbitCamera {
+    pub target: glm::Vec3,
+    pub distance: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    pub world_up: glm::Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub aspect: f32,
+    pub fovy: f32,
+    pub znear: f32,
+    pub zfar: f32,
+}
+
+impl OrbitCamera {
+    // `distance` must stay positive, or `get_front` would need to
+    // normalize a zero-length vector once the eye reaches the target.
+    const MIN_DISTANCE: f32 = 0.01;
+
+    pub fn new(
+        target: glm::Vec3,
+        distance: f32,
+        min_distance: f32,
+        max_distance: f32,
+        world_up: glm::Vec3,
+        aspect: f32,
+        fovy: f32,
+        znear: f32,
+        zfar: f32,
+    ) -> Self {
+        let min_distance = min_distance.max(Self::MIN_DISTANCE);
+        OrbitCamera {
+            target,
+            distance: distance.clamp(min_distance, max_distance),
+            min_distance,
+            max_distance,
+            world_up,
+            yaw: 0.0,
+            pitch: 0.0,
+            aspect,
+            fovy,
+            znear,
+            zfar,
         }
-        let view = glm::look_at(
-            &self.position,
-            &(self.position + self.get_front()),
-            &self.world_up
-        );
+    }
+
+    pub fn get_eye(&self) -> glm::Vec3 {
+        self.target + self.distance * glm::vec3(
+            self.pitch.cos() * self.yaw.cos(),
+            self.pitch.sin(),
+            self.pitch.cos() * self.yaw.sin()
+        )
+    }
+}
+
+impl Camera for OrbitCamera {
+    fn get_view_projection_matrix(&self) -> glm::Mat4 {
+        let view = glm::look_at(&self.get_eye(), &self.target, &self.world_up);
         let projection = glm::perspective(
             self.aspect,
             self.fovy,
             self.znear,
             self.zfar
         );
-        *OPENGL_TO_WGPU_MATRIX * projection * view
+        opengl_to_wgpu_matrix() * projection * view
+    }
+
+    fn set_aspect(&mut self, aspect: f32) {
+        self.aspect = aspect;
+    }
+
+    fn get_front(&self) -> glm::Vec3 {
+        glm::normalize(&(self.target - self.get_eye()))
+    }
+
+    fn get_world_up(&self) -> glm::Vec3 {
+        self.world_up
+    }
+
+    fn translate(&mut self, offset: glm::Vec3) {
+        // Pan: move the point we're orbiting around rather than the eye.
+        self.target += offset;
+    }
+
+    fn rotate(&mut self, dyaw: f32, dpitch: f32) {
+        rotate_yaw_pitch(&mut self.yaw, &mut self.pitch, dyaw, dpitch);
+    }
+
+    fn zoom(&mut self, delta: f32) {
+        self.distance -= delta;
+        self.distance = self.distance.clamp(self.min_distance, self.max_distance);
     }
 }
 
@@ -84,11 +238,31 @@ pub struct CameraController {
     is_right_pressed: bool,
     is_up_pressed: bool,
     is_down_pressed: bool,
+    is_sprint_pressed: bool,
+    is_zoom_modifier_pressed: bool,
+    run_multiplier: f32,
+    cursor_grabbed: bool,
+    speed_scale: f32,
     mouse_delta: (f32, f32),
     mouse_scroll_delta: f32,
+    zoom_scroll_delta: f32,
+    last_update: Instant,
+    velocity: glm::Vec3,
+    smooth_movement: Option<SmoothMovement>,
+}
+
+// Parameters for the opt-in smooth movement mode: key presses apply
+// thrust to a velocity that glides to a stop instead of snapping the
+// camera's position directly.
+struct SmoothMovement {
+    thrust_mag: f32,
+    damping_half_life: f32,
 }
 
 impl CameraController {
+    const MIN_SPEED_SCALE: f32 = 0.01;
+    const MAX_SPEED_SCALE: f32 = 100.0;
+
     pub fn new(move_speed: f32, mouse_sensitivity: f32, zoom_sensitivity: f32) -> Self {
         CameraController {
             move_speed,
@@ -100,11 +274,54 @@ impl CameraController {
             is_right_pressed: false,
             is_up_pressed: false,
             is_down_pressed: false,
+            is_sprint_pressed: false,
+            is_zoom_modifier_pressed: false,
+            run_multiplier: 2.0,
+            cursor_grabbed: true,
+            speed_scale: 1.0,
             mouse_delta: (0.0, 0.0),
             mouse_scroll_delta: 0.0,
+            zoom_scroll_delta: 0.0,
+            last_update: Instant::now(),
+            velocity: glm::vec3(0.0, 0.0, 0.0),
+            smooth_movement: None,
         }
     }
 
+    pub fn with_run_multiplier(mut self, run_multiplier: f32) -> Self {
+        self.run_multiplier = run_multiplier;
+        self
+    }
+
+    // Mouse-look only applies while this is `true`; toggled by pressing
+    // Tab so users can Alt-Tab away or interact with UI.
+    pub fn wants_cursor_grab(&self) -> bool {
+        self.cursor_grabbed
+    }
+
+    // Like `new`, but enables smooth movement via `thrust_mag` and
+    // `damping_half_life` (see `SmoothMovement`).
+    pub fn new_smooth(
+        move_speed: f32,
+        mouse_sensitivity: f32,
+        zoom_sensitivity: f32,
+        thrust_mag: f32,
+        damping_half_life: f32,
+    ) -> Self {
+        let mut res = Self::new(move_speed, mouse_sensitivity, zoom_sensitivity);
+        res.smooth_movement = Some(SmoothMovement { thrust_mag, damping_half_life });
+        res
+    }
+
+    // Time elapsed since the last call to `tick` (or since construction),
+    // for use as the `dt` passed to `update_camera`.
+    pub fn tick(&mut self) -> std::time::Duration {
+        let now = Instant::now();
+        let elapsed = now - self.last_update;
+        self.last_update = now;
+        elapsed
+    }
+
     pub fn process_event(&mut self, event: &DeviceEvent) -> bool {
         match event {
             DeviceEvent::Key(keyboard_input) => {
@@ -135,18 +352,38 @@ impl CameraController {
                         self.is_down_pressed = is_pressed;
                         true
                     },
+                    VirtualKeyCode::LShift => {
+                        self.is_sprint_pressed = is_pressed;
+                        true
+                    },
+                    VirtualKeyCode::LAlt => {
+                        self.is_zoom_modifier_pressed = is_pressed;
+                        true
+                    },
+                    VirtualKeyCode::Tab => {
+                        if is_pressed {
+                            self.cursor_grabbed = !self.cursor_grabbed;
+                        }
+                        true
+                    },
                     _ => false,
                 }
             },
             DeviceEvent::MouseMotion { delta } => {
-                self.mouse_delta.0 += delta.0 as f32;
-                self.mouse_delta.1 += delta.1 as f32;
+                if self.cursor_grabbed {
+                    self.mouse_delta.0 += delta.0 as f32;
+                    self.mouse_delta.1 += delta.1 as f32;
+                }
                 true
             },
             DeviceEvent::MouseWheel { delta } => {
                 match delta {
                     MouseScrollDelta::LineDelta(_, delta_y) => {
-                        self.mouse_scroll_delta += delta_y;
+                        if self.is_zoom_modifier_pressed {
+                            self.zoom_scroll_delta += delta_y;
+                        } else {
+                            self.mouse_scroll_delta += delta_y;
+                        }
                         true
                     },
                     _ => false
@@ -156,11 +393,26 @@ impl CameraController {
         }
     }
 
-    pub fn update_camera(&mut self, camera: &mut Camera) {
+    pub fn process_window_event(&mut self, event: &WindowEvent, camera: &mut dyn Camera) -> bool {
+        match event {
+            WindowEvent::Resized(size) => {
+                camera.resize(size.width, size.height);
+                true
+            },
+            WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+                camera.resize(new_inner_size.width, new_inner_size.height);
+                true
+            },
+            _ => false
+        }
+    }
+
+    pub fn update_camera(&mut self, camera: &mut dyn Camera, dt: std::time::Duration) {
+        let dt = dt.as_secs_f32();
         let forward_dir = camera.get_front();
-        let up_dir = camera.world_up;
+        let up_dir = camera.get_world_up();
         let right_dir = glm::cross(&forward_dir, &up_dir);
-        
+
         // Translation
         let mut move_dir = glm::vec3(0.0, 0.0, 0.0);
         if self.is_forward_pressed {
@@ -184,18 +436,111 @@ impl CameraController {
         if self.is_down_pressed {
             move_dir -= up_dir;
         }
-        camera.position += self.move_speed * move_dir;
+        let sprint_multiplier = if self.is_sprint_pressed { self.run_multiplier } else { 1.0 };
+        let speed_multiplier = sprint_multiplier * self.speed_scale;
+        match &self.smooth_movement {
+            Some(smooth) => {
+                self.velocity += speed_multiplier * smooth.thrust_mag * move_dir * dt;
+                self.velocity *= 0.5_f32.powf(dt / smooth.damping_half_life);
+                camera.translate(self.velocity * dt);
+            },
+            None => {
+                camera.translate(speed_multiplier * self.move_speed * dt * move_dir);
+            },
+        }
 
-        // Rotation
-        camera.yaw += self.mouse_sensitivity * self.mouse_delta.0;
-        camera.yaw %= 2.0 * std::f32::consts::PI;
-        camera.pitch -= self.mouse_sensitivity * self.mouse_delta.1;
-        camera.pitch = camera.pitch.clamp(-89.0_f32.to_radians(), 89.0_f32.to_radians());
+        // Rotation (only while the cursor is grabbed, so mouse movement
+        // doesn't spin the camera while interacting with other windows/UI)
+        if self.cursor_grabbed {
+            camera.rotate(
+                self.mouse_sensitivity * self.mouse_delta.0,
+                -self.mouse_sensitivity * self.mouse_delta.1
+            );
+        }
         self.mouse_delta = (0.0, 0.0);
 
-        // Zooming
-        camera.fovy += self.zoom_sensitivity * self.mouse_scroll_delta;
-        camera.fovy = camera.fovy.clamp(1.0_f32.to_radians(), 120.0_f32.to_radians());
+        // Scrolling adjusts move speed logarithmically by default; holding
+        // the zoom modifier routes it to fovy/distance zoom instead. This
+        // is a multiplier on `move_speed`/`thrust_mag` rather than a direct
+        // mutation, so it applies equally to smooth and non-smooth movement.
+        self.speed_scale *= (self.mouse_scroll_delta * 0.1).exp();
+        self.speed_scale = self.speed_scale.clamp(Self::MIN_SPEED_SCALE, Self::MAX_SPEED_SCALE);
         self.mouse_scroll_delta = 0.0;
+
+        camera.zoom(self.zoom_sensitivity * self.zoom_scroll_delta);
+        self.zoom_scroll_delta = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn test_camera() -> FpsCamera {
+        FpsCamera::new(
+            glm::vec3(0.0, 0.0, 0.0),
+            glm::vec3(0.0, 1.0, 0.0),
+            glm::vec3(1.0, 0.0, 0.0),
+            16.0 / 9.0,
+            45.0_f32.to_radians(),
+            0.1,
+            100.0,
+        )
+    }
+
+    #[test]
+    fn resize_ignores_zero_height() {
+        let mut camera = test_camera();
+        let original_aspect = camera.aspect;
+        camera.resize(800, 0);
+        assert_eq!(camera.aspect, original_aspect);
+        camera.resize(800, 600);
+        assert_eq!(camera.aspect, 800.0 / 600.0);
+    }
+
+    #[test]
+    fn rotation_is_not_rescaled_by_dt() {
+        let mut controller_a = CameraController::new(1.0, 0.01, 0.1);
+        controller_a.mouse_delta = (10.0, 0.0);
+        let mut camera_a = test_camera();
+        controller_a.update_camera(&mut camera_a, Duration::from_secs_f32(1.0 / 240.0));
+
+        let mut controller_b = CameraController::new(1.0, 0.01, 0.1);
+        controller_b.mouse_delta = (10.0, 0.0);
+        let mut camera_b = test_camera();
+        controller_b.update_camera(&mut camera_b, Duration::from_secs_f32(1.0));
+
+        assert!((camera_a.yaw - camera_b.yaw).abs() < 1e-6);
+    }
+
+    #[test]
+    fn zoom_is_not_rescaled_by_dt() {
+        let mut controller_a = CameraController::new(1.0, 0.01, 0.1);
+        controller_a.zoom_scroll_delta = 1.0;
+        let mut camera_a = test_camera();
+        controller_a.update_camera(&mut camera_a, Duration::from_secs_f32(1.0 / 240.0));
+
+        let mut controller_b = CameraController::new(1.0, 0.01, 0.1);
+        controller_b.zoom_scroll_delta = 1.0;
+        let mut camera_b = test_camera();
+        controller_b.update_camera(&mut camera_b, Duration::from_secs_f32(1.0));
+
+        assert!((camera_a.fovy - camera_b.fovy).abs() < 1e-6);
+    }
+
+    #[test]
+    fn speed_scale_is_clamped_to_bounds() {
+        let mut controller = CameraController::new(1.0, 0.01, 0.1);
+        controller.mouse_scroll_delta = 1000.0;
+        let mut camera = test_camera();
+        controller.update_camera(&mut camera, Duration::from_secs_f32(1.0 / 60.0));
+        assert_eq!(controller.speed_scale, CameraController::MAX_SPEED_SCALE);
+
+        let mut controller = CameraController::new(1.0, 0.01, 0.1);
+        controller.mouse_scroll_delta = -1000.0;
+        let mut camera = test_camera();
+        controller.update_camera(&mut camera, Duration::from_secs_f32(1.0 / 60.0));
+        assert_eq!(controller.speed_scale, CameraController::MIN_SPEED_SCALE);
     }
 }